@@ -1,6 +1,8 @@
 #![no_std]
 #![no_main]
+#![feature(abi_x86_interrupt)]
 
+use core::arch::asm;
 use core::fmt::{self, Write};
 use core::panic::PanicInfo;
 use spin::Mutex;
@@ -9,19 +11,74 @@ const VGA: usize = 0xb8000;
 const W: usize = 80;
 const H: usize = 25;
 
+/// Write a byte to an I/O port.
+unsafe fn outb(port: u16, val: u8) {
+    asm!("out dx, al", in("dx") port, in("al") val, options(nomem, nostack, preserves_flags));
+}
+
+/// Read a byte from an I/O port.
+unsafe fn inb(port: u16) -> u8 {
+    let val: u8;
+    asm!("in al, dx", out("al") val, in("dx") port, options(nomem, nostack, preserves_flags));
+    val
+}
+
 #[repr(u8)]
 #[derive(Clone, Copy)]
-enum Color { Black = 0, Green = 2, LightGreen = 10, White = 15 }
+enum Color {
+    Black = 0,
+    Blue = 1,
+    Green = 2,
+    Cyan = 3,
+    Red = 4,
+    Magenta = 5,
+    Brown = 6,
+    LightGray = 7,
+    DarkGray = 8,
+    LightBlue = 9,
+    LightGreen = 10,
+    LightCyan = 11,
+    LightRed = 12,
+    Pink = 13,
+    Yellow = 14,
+    White = 15,
+}
 
 #[repr(C)]
 #[derive(Clone, Copy)]
 struct Char { ascii: u8, color: u8 }
 
-struct Writer { col: usize, row: usize, color: u8 }
+/// Default attribute byte: light-green foreground on a black background.
+const DEFAULT_COLOR: u8 = (Color::Black as u8) << 4 | Color::LightGreen as u8;
+
+/// Maps an ANSI SGR color index (0..=7) to the corresponding VGA palette index.
+const ANSI_TO_VGA: [u8; 8] = [0, 4, 2, 6, 1, 5, 3, 7];
+
+const MAX_PARAMS: usize = 8;
+
+/// State of the `ESC[...m` escape-sequence parser driven from `write_byte`.
+#[derive(Clone, Copy, PartialEq)]
+enum AnsiState { Ground, Escape, CsiParam }
+
+struct Writer {
+    col: usize,
+    row: usize,
+    color: u8,
+    state: AnsiState,
+    params: [u16; MAX_PARAMS],
+    nparams: usize,
+}
 
 impl Writer {
     const fn new() -> Self {
-        Self { col: 0, row: 0, color: (Color::Black as u8) << 4 | Color::LightGreen as u8 }
+        Self {
+            col: 0,
+            row: 0,
+            color: DEFAULT_COLOR,
+            state: AnsiState::Ground,
+            params: [0; MAX_PARAMS],
+            nparams: 0,
+        }
     }
 
     fn put(&self, row: usize, col: usize, ch: Char) {
@@ -41,31 +98,143 @@ impl Writer {
         self.row = 0;
     }
 
+    /// Shift every row up by one, blank the last row, and step `row` back so
+    /// the cursor stays on the final line — ordinary terminal scrolling.
+    fn scroll_up(&mut self) {
+        for r in 1..H {
+            for c in 0..W {
+                unsafe {
+                    let src = (VGA as *const Char).add(r * W + c);
+                    let dst = (VGA as *mut Char).add((r - 1) * W + c);
+                    core::ptr::write_volatile(dst, core::ptr::read_volatile(src));
+                }
+            }
+        }
+        for c in 0..W {
+            self.put(H - 1, c, Char { ascii: b' ', color: self.color });
+        }
+        self.row -= 1;
+    }
+
     fn write_byte(&mut self, byte: u8) {
         if byte == b'\n' {
             self.col = 0;
             self.row += 1;
+            if self.row >= H {
+                self.scroll_up();
+            }
             return;
         }
         if self.col >= W {
             self.col = 0;
             self.row += 1;
         }
-        if self.row < H {
-            self.put(self.row, self.col, Char { ascii: byte, color: self.color });
-            self.col += 1;
+        if self.row >= H {
+            self.scroll_up();
         }
+        self.put(self.row, self.col, Char { ascii: byte, color: self.color });
+        self.col += 1;
     }
 
     fn set_color(&mut self, fg: Color, bg: Color) {
         self.color = (bg as u8) << 4 | fg as u8;
     }
+
+    /// Remove the last character on the current line, if any.
+    fn backspace(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+            self.put(self.row, self.col, Char { ascii: b' ', color: self.color });
+        }
+    }
+
+    /// Point the VGA hardware cursor at the current `row`/`col` via the CRTC
+    /// cursor-location registers (`0x0E`/`0x0F`) on ports `0x3D4`/`0x3D5`.
+    fn update_cursor(&self) {
+        let pos = self.row * W + self.col;
+        unsafe {
+            outb(0x3D4, 0x0F);
+            outb(0x3D5, (pos & 0xFF) as u8);
+            outb(0x3D4, 0x0E);
+            outb(0x3D5, ((pos >> 8) & 0xFF) as u8);
+        }
+    }
+
+    /// Feed a raw byte through the escape-sequence state machine, emitting only
+    /// real printable text to the framebuffer and consuming `ESC[...m` runs.
+    fn feed(&mut self, byte: u8) {
+        match self.state {
+            AnsiState::Ground => {
+                if byte == 0x1b {
+                    self.state = AnsiState::Escape;
+                } else {
+                    self.write_byte(if matches!(byte, 0x20..=0x7e | b'\n') { byte } else { 0xfe });
+                }
+            }
+            AnsiState::Escape => {
+                if byte == b'[' {
+                    self.params = [0; MAX_PARAMS];
+                    self.nparams = 0;
+                    self.state = AnsiState::CsiParam;
+                } else {
+                    // Not a CSI we understand; drop the escape together with
+                    // this byte and resume.
+                    self.state = AnsiState::Ground;
+                }
+            }
+            AnsiState::CsiParam => match byte {
+                b'0'..=b'9' => {
+                    if self.nparams == 0 {
+                        self.nparams = 1;
+                    }
+                    let i = (self.nparams - 1).min(MAX_PARAMS - 1);
+                    self.params[i] = self.params[i].wrapping_mul(10) + (byte - b'0') as u16;
+                }
+                b';' => {
+                    if self.nparams < MAX_PARAMS {
+                        self.nparams += 1;
+                    }
+                }
+                b'm' => {
+                    self.apply_sgr();
+                    self.state = AnsiState::Ground;
+                }
+                _ => {
+                    // Unsupported CSI final byte; ignore the whole sequence.
+                    self.state = AnsiState::Ground;
+                }
+            },
+        }
+    }
+
+    /// Apply the accumulated SGR parameters to the active color attribute.
+    fn apply_sgr(&mut self) {
+        let count = if self.nparams == 0 { 1 } else { self.nparams };
+        for &p in &self.params[..count] {
+            match p {
+                0 => self.color = DEFAULT_COLOR,
+                30..=37 => {
+                    let fg = ANSI_TO_VGA[(p - 30) as usize];
+                    self.color = (self.color & 0xf0) | fg;
+                }
+                40..=47 => {
+                    let bg = ANSI_TO_VGA[(p - 40) as usize];
+                    self.color = (self.color & 0x0f) | (bg << 4);
+                }
+                90..=97 => {
+                    let fg = ANSI_TO_VGA[(p - 90) as usize] | 0x08;
+                    self.color = (self.color & 0xf0) | fg;
+                }
+                _ => {}
+            }
+        }
+    }
 }
 
 impl fmt::Write for Writer {
     fn write_str(&mut self, s: &str) -> fmt::Result {
         for b in s.bytes() {
-            self.write_byte(if matches!(b, 0x20..=0x7e | b'\n') { b } else { 0xfe });
+            self.feed(b);
         }
         Ok(())
     }
@@ -73,9 +242,440 @@ impl fmt::Write for Writer {
 
 static WRITER: Mutex<Writer> = Mutex::new(Writer::new());
 
+const COM1: u16 = 0x3F8;
+
+/// A 16550-compatible UART on COM1, used to mirror console output over serial.
+struct Serial { base: u16, initialized: bool }
+
+impl Serial {
+    const fn new(base: u16) -> Self {
+        Self { base, initialized: false }
+    }
+
+    fn init(&mut self) {
+        unsafe {
+            outb(self.base + 1, 0x00); // disable interrupts
+            outb(self.base + 3, 0x80); // enable divisor latch (LCR bit 7)
+            outb(self.base, 0x03);     // divisor low byte: 38400 baud
+            outb(self.base + 1, 0x00); // divisor high byte
+            outb(self.base + 3, 0x03); // 8 bits, no parity, one stop bit (8N1)
+            outb(self.base + 2, 0x07); // enable FIFO, clear buffers
+        }
+        self.initialized = true;
+    }
+
+    fn transmit_empty(&self) -> bool {
+        unsafe { inb(self.base + 5) & 0x20 != 0 }
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        if !self.initialized {
+            self.init();
+        }
+        while !self.transmit_empty() {}
+        unsafe { outb(self.base, byte); }
+    }
+}
+
+impl fmt::Write for Serial {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for b in s.bytes() {
+            self.write_byte(b);
+        }
+        Ok(())
+    }
+}
+
+static SERIAL: Mutex<Serial> = Mutex::new(Serial::new(COM1));
+
 macro_rules! println {
-    () => ({ let _ = write!(WRITER.lock(), "\n"); });
-    ($($arg:tt)*) => ({ let _ = writeln!(WRITER.lock(), $($arg)*); });
+    () => ({
+        let _ = write!(WRITER.lock(), "\n");
+        let _ = write!(SERIAL.lock(), "\n");
+    });
+    ($($arg:tt)*) => ({
+        let _ = writeln!(WRITER.lock(), $($arg)*);
+        let _ = writeln!(SERIAL.lock(), $($arg)*);
+    });
+}
+
+/// Emit a leveled log line: a colored bracketed tag matching the boot-banner
+/// style, the message in the default color, and the prior color restored.
+macro_rules! log {
+    ($tag:expr, $fg:expr, $($arg:tt)*) => ({
+        {
+            let mut w = WRITER.lock();
+            let prev = w.color;
+            w.set_color($fg, Color::Black);
+            let _ = w.write_str($tag);
+            w.color = DEFAULT_COLOR;
+            let _ = writeln!(w, " {}", format_args!($($arg)*));
+            w.color = prev;
+        }
+        // Mirror the tag + message over serial (uncolored) so headless runs
+        // capture leveled diagnostics too.
+        let _ = writeln!(SERIAL.lock(), "{} {}", $tag, format_args!($($arg)*));
+    });
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => ({ log!("[OK]", Color::Green, $($arg)*); });
+}
+
+macro_rules! warn {
+    ($($arg:tt)*) => ({ log!("[WARN]", Color::Yellow, $($arg)*); });
+}
+
+macro_rules! error {
+    ($($arg:tt)*) => ({ log!("[ERR]", Color::Red, $($arg)*); });
+}
+
+// ---------------------------------------------------------------------------
+// 8259 PIC, IDT and the PS/2 keyboard-driven shell
+// ---------------------------------------------------------------------------
+
+const PIC1_CMD: u16 = 0x20;
+const PIC1_DATA: u16 = 0x21;
+const PIC2_CMD: u16 = 0xA0;
+const PIC2_DATA: u16 = 0xA1;
+const PIC_EOI: u8 = 0x20;
+
+/// Vector where IRQ1 (keyboard) lands once the master PIC is remapped to 0x20.
+const INT_KEYBOARD: usize = 0x21;
+
+/// Remap the 8259 PICs to vectors `0x20..0x30` and unmask only IRQ1 so that a
+/// keypress raises a `0x21` interrupt instead of colliding with CPU exceptions.
+fn init_pic() {
+    unsafe {
+        outb(PIC1_CMD, 0x11); // start init, expect ICW4
+        outb(PIC2_CMD, 0x11);
+        outb(PIC1_DATA, 0x20); // master vector offset
+        outb(PIC2_DATA, 0x28); // slave vector offset
+        outb(PIC1_DATA, 0x04); // tell master the slave sits on IRQ2
+        outb(PIC2_DATA, 0x02); // tell slave its cascade identity
+        outb(PIC1_DATA, 0x01); // 8086/88 mode
+        outb(PIC2_DATA, 0x01);
+        outb(PIC1_DATA, 0xFD); // mask everything except IRQ1 on the master
+        outb(PIC2_DATA, 0xFF); // mask the whole slave
+    }
+}
+
+/// A 32-bit protected-mode interrupt gate descriptor.
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+struct IdtEntry {
+    offset_low: u16,
+    selector: u16,
+    zero: u8,
+    type_attr: u8,
+    offset_high: u16,
+}
+
+impl IdtEntry {
+    const fn missing() -> Self {
+        Self { offset_low: 0, selector: 0, zero: 0, type_attr: 0, offset_high: 0 }
+    }
+
+    fn set(&mut self, handler: extern "x86-interrupt" fn()) {
+        let addr = handler as usize;
+        self.offset_low = (addr & 0xFFFF) as u16;
+        self.offset_high = ((addr >> 16) & 0xFFFF) as u16;
+        self.selector = 0x08; // kernel code segment
+        self.zero = 0;
+        self.type_attr = 0x8E; // present, ring 0, 32-bit interrupt gate
+    }
+}
+
+#[repr(C, packed)]
+struct IdtPointer { limit: u16, base: u32 }
+
+static mut IDT: [IdtEntry; 256] = [IdtEntry::missing(); 256];
+
+/// Build the IDT and load it with `lidt`. Interrupts stay masked until
+/// `enable_interrupts` is called, so early boot output can lock `WRITER`
+/// without racing the IRQ1 handler on a non-reentrant `spin::Mutex`.
+fn init_idt() {
+    unsafe {
+        IDT[INT_KEYBOARD].set(keyboard_interrupt);
+        let ptr = IdtPointer {
+            limit: (core::mem::size_of::<[IdtEntry; 256]>() - 1) as u16,
+            base: IDT.as_ptr() as u32,
+        };
+        asm!("lidt [{}]", in(reg) &ptr, options(readonly, nostack, preserves_flags));
+    }
+}
+
+/// Unmask CPU interrupts. Call this only once boot output is done and the
+/// shell prompt is drawn, so the keyboard handler never preempts a `WRITER`
+/// holder.
+fn enable_interrupts() {
+    unsafe { asm!("sti", options(nomem, nostack)); }
+}
+
+/// Set-1 scancode → ASCII, unshifted (indexed by make code `0x00..0x40`).
+const SCANCODE_MAP: [u8; 0x40] = [
+    0, 0, b'1', b'2', b'3', b'4', b'5', b'6', b'7', b'8', b'9', b'0', b'-', b'=', 0x08, b'\t',
+    b'q', b'w', b'e', b'r', b't', b'y', b'u', b'i', b'o', b'p', b'[', b']', b'\n', 0, b'a', b's',
+    b'd', b'f', b'g', b'h', b'j', b'k', b'l', b';', b'\'', b'`', 0, b'\\', b'z', b'x', b'c', b'v',
+    b'b', b'n', b'm', b',', b'.', b'/', 0, b'*', 0, b' ', 0, 0, 0, 0, 0, 0,
+];
+
+/// Set-1 scancode → ASCII, with Shift held.
+const SCANCODE_MAP_SHIFT: [u8; 0x40] = [
+    0, 0, b'!', b'@', b'#', b'$', b'%', b'^', b'&', b'*', b'(', b')', b'_', b'+', 0x08, b'\t',
+    b'Q', b'W', b'E', b'R', b'T', b'Y', b'U', b'I', b'O', b'P', b'{', b'}', b'\n', 0, b'A', b'S',
+    b'D', b'F', b'G', b'H', b'J', b'K', b'L', b':', b'"', b'~', 0, b'|', b'Z', b'X', b'C', b'V',
+    b'B', b'N', b'M', b'<', b'>', b'?', 0, b'*', 0, b' ', 0, 0, 0, 0, 0, 0,
+];
+
+/// The interactive console: a single line buffer plus Shift state.
+struct Shell { buf: [u8; W], len: usize, shift: bool }
+
+impl Shell {
+    const fn new() -> Self {
+        Self { buf: [0; W], len: 0, shift: false }
+    }
+
+    /// Translate one scancode and, for printable keys, echo and buffer it;
+    /// Enter dispatches the line and Backspace trims it.
+    fn on_scancode(&mut self, sc: u8) {
+        match sc {
+            0x2A | 0x36 => self.shift = true,
+            0xAA | 0xB6 => self.shift = false,
+            _ if sc < 0x40 => {
+                let ch = if self.shift {
+                    SCANCODE_MAP_SHIFT[sc as usize]
+                } else {
+                    SCANCODE_MAP[sc as usize]
+                };
+                match ch {
+                    0 => {}
+                    b'\n' => self.submit(),
+                    0x08 => {
+                        if self.len > 0 {
+                            self.len -= 1;
+                            let mut w = WRITER.lock();
+                            w.backspace();
+                            w.update_cursor();
+                        }
+                    }
+                    _ => {
+                        if self.len < W {
+                            self.buf[self.len] = ch;
+                            self.len += 1;
+                            let mut w = WRITER.lock();
+                            w.write_byte(ch);
+                            w.update_cursor();
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn submit(&mut self) {
+        println!();
+        let line = &self.buf[..self.len];
+        dispatch(line);
+        self.len = 0;
+        let mut w = WRITER.lock();
+        let _ = w.write_str("> ");
+        w.update_cursor();
+    }
+}
+
+static SHELL: Mutex<Shell> = Mutex::new(Shell::new());
+
+/// Map a color name to a palette entry, for the `color` command.
+fn color_from_name(name: &[u8]) -> Option<Color> {
+    match name {
+        b"black" => Some(Color::Black),
+        b"blue" => Some(Color::Blue),
+        b"green" => Some(Color::Green),
+        b"cyan" => Some(Color::Cyan),
+        b"red" => Some(Color::Red),
+        b"magenta" => Some(Color::Magenta),
+        b"brown" => Some(Color::Brown),
+        b"lightgray" => Some(Color::LightGray),
+        b"darkgray" => Some(Color::DarkGray),
+        b"lightblue" => Some(Color::LightBlue),
+        b"lightgreen" => Some(Color::LightGreen),
+        b"lightcyan" => Some(Color::LightCyan),
+        b"lightred" => Some(Color::LightRed),
+        b"pink" => Some(Color::Pink),
+        b"yellow" => Some(Color::Yellow),
+        b"white" => Some(Color::White),
+        _ => None,
+    }
+}
+
+/// Parse an unsigned integer in the given radix, or `None` on any bad digit.
+fn parse_radix(s: &[u8], radix: usize) -> Option<usize> {
+    if s.is_empty() {
+        return None;
+    }
+    let mut v = 0usize;
+    for &c in s {
+        let d = match c {
+            b'0'..=b'9' => (c - b'0') as usize,
+            b'a'..=b'f' => (c - b'a' + 10) as usize,
+            b'A'..=b'F' => (c - b'A' + 10) as usize,
+            _ => return None,
+        };
+        if d >= radix {
+            return None;
+        }
+        v = v * radix + d;
+    }
+    Some(v)
+}
+
+/// Run a completed command line against the built-in command table.
+fn dispatch(line: &[u8]) {
+    // Split off the first whitespace-delimited word as the command name.
+    let mut i = 0;
+    while i < line.len() && line[i] == b' ' {
+        i += 1;
+    }
+    let start = i;
+    while i < line.len() && line[i] != b' ' {
+        i += 1;
+    }
+    let cmd = &line[start..i];
+    while i < line.len() && line[i] == b' ' {
+        i += 1;
+    }
+    let rest = &line[i..];
+
+    match cmd {
+        b"" => {}
+        b"help" => {
+            println!("commands: help, clear, color <fg> <bg>, echo <text>, hexdump <hexaddr> <len>");
+        }
+        b"clear" => {
+            WRITER.lock().clear();
+        }
+        b"echo" => {
+            let mut w = WRITER.lock();
+            for &b in rest {
+                w.write_byte(b);
+            }
+            let _ = w.write_str("\n");
+        }
+        b"color" => {
+            // Take the first two space-separated color names; any further
+            // words are ignored.
+            let mut parts = rest.split(|&b| b == b' ').filter(|p| !p.is_empty());
+            match (parts.next(), parts.next()) {
+                (Some(fg), Some(bg)) => match (color_from_name(fg), color_from_name(bg)) {
+                    (Some(fg), Some(bg)) => WRITER.lock().set_color(fg, bg),
+                    _ => error!("color: unknown color name"),
+                },
+                _ => println!("usage: color <fg> <bg>"),
+            }
+        }
+        b"hexdump" => {
+            let mut parts = rest.split(|&b| b == b' ').filter(|p| !p.is_empty());
+            match (parts.next(), parts.next()) {
+                (Some(a), Some(l)) => {
+                    let a = if a.starts_with(b"0x") { &a[2..] } else { a };
+                    match (parse_radix(a, 16), parse_radix(l, 10)) {
+                        (Some(addr), Some(len)) => hexdump(addr, len),
+                        _ => warn!("hexdump: bad address or length"),
+                    }
+                }
+                _ => println!("usage: hexdump <hexaddr> <len>"),
+            }
+        }
+        _ => {
+            let mut w = WRITER.lock();
+            let _ = w.write_str("unknown command: ");
+            for &b in cmd {
+                w.write_byte(b);
+            }
+            let _ = w.write_str("\n");
+        }
+    }
+}
+
+const HEX: [u8; 16] = *b"0123456789abcdef";
+
+/// Pick a foreground color for a byte the way hex viewers do, so structure
+/// jumps out: nulls gray, whitespace yellow, printable green, the rest red.
+fn byte_color(b: u8) -> u8 {
+    match b {
+        0 => 0x08,                                           // dark gray: null
+        b'\t' | b'\n' | 0x0b | 0x0c | b'\r' | b' ' => 0x0e,  // yellow: whitespace
+        0x21..=0x7e => 0x0a,                                 // light green: printable
+        _ => 0x0c,                                           // light red: other
+    }
+}
+
+fn put_hex_u8(w: &mut Writer, b: u8) {
+    w.write_byte(HEX[(b >> 4) as usize]);
+    w.write_byte(HEX[(b & 0x0f) as usize]);
+}
+
+fn put_hex_usize(w: &mut Writer, v: usize, digits: usize) {
+    for i in (0..digits).rev() {
+        w.write_byte(HEX[((v >> (i * 4)) & 0xf) as usize]);
+    }
+}
+
+/// Render `len` bytes starting at `addr` as a classic 16-byte-per-row hex+ASCII
+/// panel, coloring each cell by byte category via the `Writer` color machinery.
+fn hexdump(addr: usize, len: usize) {
+    let mut w = WRITER.lock();
+    let structure = w.color;
+    let mut off = 0;
+    while off < len {
+        w.color = structure;
+        put_hex_usize(&mut w, addr + off, 8);
+        let _ = w.write_str("  ");
+
+        // Hex column, grouped 8 + 8.
+        for i in 0..16 {
+            if i == 8 {
+                w.color = structure;
+                w.write_byte(b' ');
+            }
+            if off + i < len {
+                let b = unsafe { core::ptr::read_volatile((addr + off + i) as *const u8) };
+                w.color = byte_color(b);
+                put_hex_u8(&mut w, b);
+            } else {
+                w.color = structure;
+                let _ = w.write_str("  ");
+            }
+            w.color = structure;
+            w.write_byte(b' ');
+        }
+
+        // ASCII gutter.
+        let _ = w.write_str(" |");
+        for i in 0..16 {
+            if off + i < len {
+                let b = unsafe { core::ptr::read_volatile((addr + off + i) as *const u8) };
+                w.color = byte_color(b);
+                w.write_byte(if matches!(b, 0x20..=0x7e) { b } else { b'.' });
+            } else {
+                w.color = structure;
+                w.write_byte(b' ');
+            }
+        }
+        w.color = structure;
+        let _ = w.write_str("|\n");
+
+        off += 16;
+    }
+    w.color = structure;
+}
+
+extern "x86-interrupt" fn keyboard_interrupt() {
+    let scancode = unsafe { inb(0x60) };
+    SHELL.lock().on_scancode(scancode);
+    unsafe { outb(PIC1_CMD, PIC_EOI); }
 }
 
 #[no_mangle]
@@ -98,17 +698,49 @@ pub extern "C" fn _start() -> ! {
     println!("  [OK] VGA text mode: 80x25");
     println!();
 
+    init_pic();
+    init_idt();
+
+    info!("Keyboard ready");
     WRITER.lock().set_color(Color::Green, Color::Black);
-    println!("  This OS does exactly one thing, and it does it well.");
     println!();
-    println!("  Now entering infinite loop. As one does.");
+    println!("  Type 'help' for commands.");
+    {
+        let mut w = WRITER.lock();
+        let _ = w.write_str("> ");
+        w.update_cursor();
+    }
 
-    loop { core::hint::spin_loop(); }
+    // Only now, with all startup output flushed, is it safe to let the
+    // keyboard handler contend for the `WRITER`/`SHELL` locks.
+    enable_interrupts();
+
+    // Idle until the next interrupt; the keyboard handler drives everything.
+    loop {
+        unsafe { asm!("hlt", options(nomem, nostack)); }
+    }
 }
 
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
     WRITER.lock().set_color(Color::White, Color::Black);
     let _ = writeln!(WRITER.lock(), "\n!!! KERNEL PANIC !!!\n{}", info);
+    let _ = writeln!(SERIAL.lock(), "\n!!! KERNEL PANIC !!!\n{}", info);
     loop { core::hint::spin_loop(); }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sgr_sets_red_foreground() {
+        // `ESC[31m` is fully consumed by the parser (no printable bytes reach
+        // the framebuffer) and must leave a red foreground in the attribute.
+        let mut w = Writer::new();
+        for &b in b"\x1b[31m" {
+            w.feed(b);
+        }
+        assert_eq!(w.color & 0x0f, Color::Red as u8);
+    }
+}